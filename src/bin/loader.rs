@@ -1,44 +1,22 @@
 use std::fs::OpenOptions;
 use std::io::Read;
-use std::slice;
-use elf_loader::{ElfFile, RelocatableSection};
+use elf_loader::ElfFile;
 
 fn main() {
     let mut file = OpenOptions::new().read(true).open("./kernel").expect("Unable to open file");
     let mut data = Vec::new();
 
     file.read_to_end(&mut data).expect("Unable to read file");
-    println!("Magic is {:X?}", &data[0..4]);
 
-    let elf = ElfFile::read(&mut data);
+    let elf = ElfFile::try_read(&mut data).expect("Not a supported elf file");
 
-    println!("Magic: {:?}", elf.is_valid());
+    println!("Entrypoint: {:#016X}", elf.entrypoint().expect("Invalid entrypoint"));
 
-    elf.section_headers().iter().for_each(|header| {
-        let header_type = header.header_type;
+    for header in elf.section_headers().expect("Invalid section header table") {
+        let name = elf.section_name(&header).unwrap_or("<unnamed>");
         let offset = header.offset;
         let size = header.size;
-        let entry_size = header.entry_size;
-        println!("{} {:#016X} - {:#016X} - {}", header_type, offset, size, entry_size)
-    });
 
-    let hdr = elf.section_headers();
-
-    for header in hdr {
-        if header.header_type != 0x4 {
-            continue;
-        }
-
-        let start_file = elf.data().as_ptr() as usize + header.offset as usize;
-        let num = header.size as usize / header.entry_size as usize;
-
-        let sections = unsafe { slice::from_raw_parts(start_file as *mut RelocatableSection, num) };
-
-        for section in sections {
-            let offset = section.offset;
-            let info = section.info;
-            let addend = section.addend;
-            println!("{:#016X}: {:#016X} = {:#16X}", offset, info, addend)
-        }
+        println!("{:<16} {:#016X} - {:#016X}", name, offset, size)
     }
-}
\ No newline at end of file
+}
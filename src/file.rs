@@ -1,10 +1,107 @@
 use core::cmp::Ordering;
-use core::slice;
 
 pub struct ElfFile<'a> {
     data: &'a mut [u8],
 }
 
+/// The PF_R/PF_W/PF_X permission bits of a program header's `flags` field
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SegmentFlags {
+    bits: u32,
+}
+
+impl SegmentFlags {
+    pub const EXECUTE: u32 = 0x1;
+    pub const WRITE: u32 = 0x2;
+    pub const READ: u32 = 0x4;
+
+    fn from_bits(bits: u32) -> SegmentFlags {
+        SegmentFlags { bits }
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.bits & Self::READ != 0
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.bits & Self::WRITE != 0
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.bits & Self::EXECUTE != 0
+    }
+}
+
+/// Backs each PT_LOAD segment with real memory, so a kernel or hypervisor can map
+/// pages with the correct permissions instead of loading into one flat writable slab
+pub trait ElfLoader {
+    /// Called once per segment before `load`, to reserve `size` bytes at `vaddr`
+    /// with the given read/write/execute permissions
+    fn allocate(&mut self, vaddr: usize, size: usize, flags: SegmentFlags);
+
+    /// Called one or more times to copy `data` into the memory starting at `vaddr`
+    fn load(&mut self, vaddr: usize, data: &[u8]);
+}
+
+/// Chunk size used to zero-fill BSS through `ElfLoader::load` without a heap allocation
+const ZERO_CHUNK: [u8; 64] = [0u8; 64];
+
+/// Errors returned while identifying or parsing an elf file
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ElfError {
+    /// The buffer is too short to contain a full elf header of its class
+    TooShort,
+    /// The magic bytes do not match the elf magic
+    InvalidMagic,
+    /// The EI_CLASS byte is not a class this crate can parse
+    UnsupportedClass(u8),
+    /// The EI_DATA byte is not an endianness this crate can parse
+    UnsupportedEndianness(u8),
+    /// A table, segment or section referenced an offset/length outside the buffer or
+    /// declared an entry size too small for its entries; `symbols` also returns this
+    /// when the file has neither a `.symtab` nor a `.dynsym` section
+    OutOfBounds,
+    /// A RELA entry's r_info named a relocation type this crate does not implement
+    UnknownRelocationType(u32),
+    /// `relocate` only implements the x86-64 relocation types, which assume ELF64-sized
+    /// addresses; it does not yet support relocating an ELF32 file
+    UnsupportedRelocationClass(Class),
+}
+
+/// The address/offset width an elf file was built for, decoded from EI_CLASS
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Class {
+    Elf32,
+    Elf64,
+}
+
+/// The minimum buffer length that can hold a full elf header of the given class:
+/// 52 bytes for ELF32's `Elf32_Ehdr`, 64 bytes for ELF64's `Elf64_Ehdr`
+fn min_header_len(class: Class) -> usize {
+    match class {
+        Class::Elf32 => 52,
+        Class::Elf64 => 64,
+    }
+}
+
+/// The byte order an elf file was built for, decoded from EI_DATA
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+/// The parsed e_ident identification header plus e_type and e_machine
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ElfIdent {
+    pub class: Class,
+    pub endianness: Endianness,
+    pub version: u8,
+    pub abi: u8,
+    pub file_type: u16,
+    pub machine: u16,
+}
+
 impl<'a> ElfFile<'a> {
     pub fn read(buffer: &'a mut [u8]) -> ElfFile<'a> {
         ElfFile {
@@ -12,6 +109,33 @@ impl<'a> ElfFile<'a> {
         }
     }
 
+    /// Reads the file, rejecting buffers that are too short, whose EI_CLASS is neither
+    /// ELF32 nor ELF64, or whose endianness this crate cannot yet parse (big-endian),
+    /// so callers don't read garbage at offsets that assume a layout the file isn't in
+    pub fn try_read(buffer: &'a mut [u8]) -> Result<ElfFile<'a>, ElfError> {
+        let file = ElfFile::read(buffer);
+
+        if file.data.len() < 5 {
+            return Err(ElfError::TooShort);
+        }
+
+        if !file.is_valid() {
+            return Err(ElfError::InvalidMagic);
+        }
+
+        if !matches!(file.data[4], 1 | 2) {
+            return Err(ElfError::UnsupportedClass(file.data[4]));
+        }
+
+        let ident = file.identify()?;
+
+        if ident.endianness != Endianness::Little {
+            return Err(ElfError::UnsupportedEndianness(file.data[5]));
+        }
+
+        Ok(file)
+    }
+
     /// Returns true if the elf file magic is valid
     pub fn is_valid(&self) -> bool {
         if self.data.len() < 4 {
@@ -27,49 +151,165 @@ impl<'a> ElfFile<'a> {
         true
     }
 
-    /// Returns the entrypoint address
-    pub fn entrypoint(&self) -> usize {
-        usize::from_le_bytes(self.data[24..32].try_into().unwrap())
+    /// Parses the e_ident identification header (bytes 4..20) plus e_type and e_machine
+    pub fn identify(&self) -> Result<ElfIdent, ElfError> {
+        if self.data.len() < 5 {
+            return Err(ElfError::TooShort);
+        }
+
+        let class = match self.data[4] {
+            1 => Class::Elf32,
+            _ => Class::Elf64,
+        };
+
+        if self.data.len() < min_header_len(class) {
+            return Err(ElfError::TooShort);
+        }
+
+        let endianness = match self.data[5] {
+            2 => Endianness::Big,
+            _ => Endianness::Little,
+        };
+
+        Ok(ElfIdent {
+            class,
+            endianness,
+            version: self.data[6],
+            abi: self.data[7],
+            file_type: u16::from_le_bytes(self.data[16..18].try_into().unwrap()),
+            machine: u16::from_le_bytes(self.data[18..20].try_into().unwrap()),
+        })
     }
 
-    /// Returns the program header table pointer, size of an entry and the number of entries
-    fn program_header_table(&self) -> (usize, u16, u16) {
-        (
-            usize::from_le_bytes(self.data[32..40].try_into().unwrap()),
-            u16::from_le_bytes(self.data[54..56].try_into().unwrap()),
-            u16::from_le_bytes(self.data[56..58].try_into().unwrap())
-        )
+    /// Returns the EI_CLASS of this file, defaulting to `Elf64` the same way `identify` does
+    fn class(&self) -> Result<Class, ElfError> {
+        Ok(match self.data.get(4).ok_or(ElfError::OutOfBounds)? {
+            1 => Class::Elf32,
+            _ => Class::Elf64,
+        })
     }
 
-    /// Returns a slice of the program headers
-    pub fn program_headers(&self) -> &[ProgramHeader] {
-        let (ptr, size, num) = self.program_header_table();
-        let end = ptr + num as usize * size as usize;
+    /// Returns the entrypoint address
+    pub fn entrypoint(&self) -> Result<usize, ElfError> {
+        match self.class()? {
+            Class::Elf32 => {
+                let bytes = self.data.get(24..28).ok_or(ElfError::OutOfBounds)?;
+                Ok(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+            }
+            Class::Elf64 => {
+                let bytes = self.data.get(24..32).ok_or(ElfError::OutOfBounds)?;
+                Ok(usize::from_le_bytes(bytes.try_into().unwrap()))
+            }
+        }
+    }
+
+    /// Returns the program header table offset, size of an entry and the number of entries,
+    /// checked to lie entirely within the buffer
+    fn program_header_table(&self) -> Result<(usize, u16, u16), ElfError> {
+        let (offset, entry_size, count) = match self.class()? {
+            Class::Elf32 => (
+                u32::from_le_bytes(self.data.get(28..32).ok_or(ElfError::OutOfBounds)?.try_into().unwrap()) as usize,
+                u16::from_le_bytes(self.data.get(42..44).ok_or(ElfError::OutOfBounds)?.try_into().unwrap()),
+                u16::from_le_bytes(self.data.get(44..46).ok_or(ElfError::OutOfBounds)?.try_into().unwrap()),
+            ),
+            Class::Elf64 => (
+                usize::from_le_bytes(self.data.get(32..40).ok_or(ElfError::OutOfBounds)?.try_into().unwrap()),
+                u16::from_le_bytes(self.data.get(54..56).ok_or(ElfError::OutOfBounds)?.try_into().unwrap()),
+                u16::from_le_bytes(self.data.get(56..58).ok_or(ElfError::OutOfBounds)?.try_into().unwrap()),
+            ),
+        };
 
-        let slice = &self.data[ptr..end];
-        let data = unsafe { slice::from_raw_parts(slice.as_ptr() as *const ProgramHeader, num as usize) };
+        let min_entry_size = match self.class()? {
+            Class::Elf32 => 32,
+            Class::Elf64 => core::mem::size_of::<ProgramHeader>(),
+        };
+
+        if (entry_size as usize) < min_entry_size {
+            return Err(ElfError::OutOfBounds);
+        }
 
-        data
+        let table_len = (entry_size as usize).checked_mul(count as usize).ok_or(ElfError::OutOfBounds)?;
+        let end = offset.checked_add(table_len).ok_or(ElfError::OutOfBounds)?;
+
+        if end > self.data.len() {
+            return Err(ElfError::OutOfBounds);
+        }
+
+        Ok((offset, entry_size, count))
     }
 
-    /// Returns the section header table pointer, size of an entry and the number of entries
-    fn section_header_table(&self) -> (usize, u16, u16) {
-        (
-            usize::from_le_bytes(self.data[40..48].try_into().unwrap()),
-            u16::from_le_bytes(self.data[58..60].try_into().unwrap()),
-            u16::from_le_bytes(self.data[60..62].try_into().unwrap())
-        )
+    /// Returns an iterator over the program headers
+    pub fn program_headers(&self) -> Result<ProgramHeaderIter<'_>, ElfError> {
+        let (offset, entry_size, count) = self.program_header_table()?;
+
+        Ok(ProgramHeaderIter {
+            data: self.data,
+            class: self.class()?,
+            offset,
+            entry_size: entry_size as usize,
+            remaining: count,
+        })
     }
 
-    /// Returns a slice of the section headers
-    pub fn section_headers(&self) -> &[SectionHeader] {
-        let (ptr, size, num) = self.section_header_table();
-        let end = ptr + num as usize * size as usize;
+    /// Returns the section header table offset, size of an entry and the number of entries,
+    /// checked to lie entirely within the buffer
+    fn section_header_table(&self) -> Result<(usize, u16, u16), ElfError> {
+        let (offset, entry_size, count) = match self.class()? {
+            Class::Elf32 => (
+                u32::from_le_bytes(self.data.get(32..36).ok_or(ElfError::OutOfBounds)?.try_into().unwrap()) as usize,
+                u16::from_le_bytes(self.data.get(46..48).ok_or(ElfError::OutOfBounds)?.try_into().unwrap()),
+                u16::from_le_bytes(self.data.get(48..50).ok_or(ElfError::OutOfBounds)?.try_into().unwrap()),
+            ),
+            Class::Elf64 => (
+                usize::from_le_bytes(self.data.get(40..48).ok_or(ElfError::OutOfBounds)?.try_into().unwrap()),
+                u16::from_le_bytes(self.data.get(58..60).ok_or(ElfError::OutOfBounds)?.try_into().unwrap()),
+                u16::from_le_bytes(self.data.get(60..62).ok_or(ElfError::OutOfBounds)?.try_into().unwrap()),
+            ),
+        };
 
-        let slice = &self.data[ptr..end];
-        let data = unsafe { slice::from_raw_parts(slice.as_ptr() as *const SectionHeader, num as usize) };
+        let min_entry_size = match self.class()? {
+            Class::Elf32 => 40,
+            Class::Elf64 => core::mem::size_of::<SectionHeader>(),
+        };
 
-        data
+        if (entry_size as usize) < min_entry_size {
+            return Err(ElfError::OutOfBounds);
+        }
+
+        let table_len = (entry_size as usize).checked_mul(count as usize).ok_or(ElfError::OutOfBounds)?;
+        let end = offset.checked_add(table_len).ok_or(ElfError::OutOfBounds)?;
+
+        if end > self.data.len() {
+            return Err(ElfError::OutOfBounds);
+        }
+
+        Ok((offset, entry_size, count))
+    }
+
+    /// Returns the section-header string table index (e_shstrndx), at byte 50 for
+    /// ELF32 and byte 62 for ELF64
+    fn shstrndx(&self) -> Result<usize, ElfError> {
+        let range = match self.class()? {
+            Class::Elf32 => 50..52,
+            Class::Elf64 => 62..64,
+        };
+
+        let bytes = self.data.get(range).ok_or(ElfError::OutOfBounds)?;
+
+        Ok(u16::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    }
+
+    /// Returns an iterator over the section headers
+    pub fn section_headers(&self) -> Result<SectionHeaderIter<'_>, ElfError> {
+        let (offset, entry_size, count) = self.section_header_table()?;
+
+        Ok(SectionHeaderIter {
+            data: self.data,
+            class: self.class()?,
+            offset,
+            entry_size: entry_size as usize,
+            remaining: count,
+        })
     }
 
     /// Returns a reference to the elf file data
@@ -77,75 +317,293 @@ impl<'a> ElfFile<'a> {
         return self.data
     }
 
-    /// Returns the length in bytes required to load all loadable segments into memory
-    pub fn load_segments_len(&self) -> usize {
-        let program_headers = self.program_headers();
+    /// Returns a section's name, resolved via the section-header string table
+    /// pointed to by e_shstrndx (bytes 50..52 for ELF32, 62..64 for ELF64)
+    pub fn section_name(&'a self, header: &SectionHeader) -> Option<&'a str> {
+        let shstrndx = self.shstrndx().ok()?;
+        let strtab = self.section_headers().ok()?.nth(shstrndx)?;
+
+        self.string_at(strtab.offset as usize, header.name_offset as usize)
+    }
+
+    /// Looks up a section by name
+    pub fn find_section(&'a self, name: &str) -> Option<SectionHeader> {
+        self.section_headers().ok()?.find(|header| self.section_name(header) == Some(name))
+    }
+
+    /// Returns a symbol's name, resolved via its symbol table's sh_link, which points
+    /// at the associated `.strtab`/`.dynstr` section
+    pub fn symbol_name(&'a self, symbol_table: &SectionHeader, symbol: &Symbol) -> Option<&'a str> {
+        let strtab = self.section_headers().ok()?.nth(symbol_table.link as usize)?;
+
+        self.string_at(strtab.offset as usize, symbol.name_offset as usize)
+    }
+
+    /// Reads a NUL-terminated string starting at `strtab_offset + index`
+    fn string_at(&'a self, strtab_offset: usize, index: usize) -> Option<&'a str> {
+        let start = strtab_offset.checked_add(index)?;
+        let rest = self.data.get(start..)?;
+        let end = start + rest.iter().position(|&byte| byte == 0)?;
+
+        core::str::from_utf8(&self.data[start..end]).ok()
+    }
+
+    /// Returns an iterator over the symbol table, preferring `.symtab` (SHT_SYMTAB)
+    /// and falling back to `.dynsym` (SHT_DYNSYM) if no static symbol table is present
+    pub fn symbols(&self) -> Result<SymbolIter<'_>, ElfError> {
+        let mut dynsym: Option<SectionHeader> = None;
+
+        for header in self.section_headers()? {
+            if header.header_type == 2 {
+                return self.symbol_iter(header);
+            }
+
+            if header.header_type == 11 && dynsym.is_none() {
+                dynsym = Some(header);
+            }
+        }
+
+        self.symbol_iter(dynsym.ok_or(ElfError::OutOfBounds)?)
+    }
+
+    fn symbol_iter(&self, header: SectionHeader) -> Result<SymbolIter<'_>, ElfError> {
+        let offset = header.offset as usize;
+        let entry_size = header.entry_size as usize;
+
+        let class = self.class()?;
+        let min_entry_size = match class {
+            Class::Elf32 => 16,
+            Class::Elf64 => core::mem::size_of::<Symbol>(),
+        };
+
+        if entry_size < min_entry_size {
+            return Err(ElfError::OutOfBounds);
+        }
+
+        let num = header.size as usize / entry_size;
+        let table_len = entry_size.checked_mul(num).ok_or(ElfError::OutOfBounds)?;
+        let end = offset.checked_add(table_len).ok_or(ElfError::OutOfBounds)?;
+
+        if end > self.data.len() {
+            return Err(ElfError::OutOfBounds);
+        }
+
+        Ok(SymbolIter {
+            data: self.data,
+            class,
+            offset,
+            entry_size,
+            remaining: num as u32,
+        })
+    }
+
+    /// Resolves a symbol table index to its `st_value`; index 0 is the reserved
+    /// undefined symbol and always resolves to 0
+    fn symbol_value(&self, index: u32) -> Result<u64, ElfError> {
+        if index == 0 {
+            return Ok(0);
+        }
+
+        self.symbols()?.nth(index as usize).map(|symbol| symbol.value).ok_or(ElfError::OutOfBounds)
+    }
 
+    /// Returns the length in bytes required to load all loadable segments into memory
+    pub fn load_segments_len(&self) -> Result<usize, ElfError> {
         let mut start = usize::MAX;
         let mut end = usize::MIN;
 
-        for header in program_headers {
-            let segment_end = header.v_addr + header.memory_size;
+        for header in self.program_headers()? {
+            if header.header_type != 0x1 {
+                continue;
+            }
+
+            let segment_end = (header.v_addr as usize).checked_add(header.memory_size as usize).ok_or(ElfError::OutOfBounds)?;
 
             if (header.v_addr as usize) < start {
                 start = header.v_addr as usize;
             }
 
-            if (segment_end as usize) > end {
-                end = segment_end as usize;
+            if segment_end > end {
+                end = segment_end;
             }
         }
 
-        end - start
+        Ok(end.saturating_sub(start))
     }
 
-
     /// Loads all PT_LOAD segments into memory starting at base
-    pub fn load(&self, base: &mut [u8]) {
-        let program_headers = self.program_headers();
+    pub fn load(&self, base: &mut [u8]) -> Result<(), ElfError> {
         let file_data = self.data();
 
-        for header in program_headers {
+        for header in self.program_headers()? {
             if header.header_type != 0x1 {
                 continue;
             }
 
-            let start = header.v_addr as usize;
+            let v_addr = header.v_addr as usize;
+            let file_offset = header.offset as usize;
+            let file_size = header.file_size as usize;
+            let memory_size = header.memory_size as usize;
 
-            let mut ptr = &mut base[start] as *mut u8;
-            let start_file = header.offset as usize;
-            let end_file = start_file + header.memory_size as usize;
+            let file_end = file_offset.checked_add(file_size).ok_or(ElfError::OutOfBounds)?;
+            let mem_end = v_addr.checked_add(memory_size).ok_or(ElfError::OutOfBounds)?;
 
-            for i in start_file..end_file {
-                unsafe {
-                    *ptr = file_data[i];
-                    ptr = ptr.add(1);
-                }
+            if file_end > file_data.len() || mem_end > base.len() || file_size > memory_size {
+                return Err(ElfError::OutOfBounds);
             }
+
+            base[v_addr..v_addr + file_size].copy_from_slice(&file_data[file_offset..file_end]);
+            base[v_addr + file_size..mem_end].fill(0);
         }
+
+        Ok(())
+    }
+
+    /// Loads all PT_LOAD segments through `loader`, which allocates memory for each
+    /// segment with its decoded permissions before the segment's bytes are copied in
+    /// and its BSS tail is zero-filled
+    pub fn load_with(&self, loader: &mut impl ElfLoader) -> Result<(), ElfError> {
+        let file_data = self.data();
+
+        for header in self.program_headers()? {
+            if header.header_type != 0x1 {
+                continue;
+            }
+
+            let v_addr = header.v_addr as usize;
+            let file_offset = header.offset as usize;
+            let file_size = header.file_size as usize;
+            let memory_size = header.memory_size as usize;
+
+            let file_end = file_offset.checked_add(file_size).ok_or(ElfError::OutOfBounds)?;
+
+            if file_end > file_data.len() || file_size > memory_size {
+                return Err(ElfError::OutOfBounds);
+            }
+
+            let flags = SegmentFlags::from_bits(header.flags);
+
+            loader.allocate(v_addr, memory_size, flags);
+            loader.load(v_addr, &file_data[file_offset..file_end]);
+
+            let mut addr = v_addr.checked_add(file_size).ok_or(ElfError::OutOfBounds)?;
+            let mut remaining = memory_size - file_size;
+
+            while remaining > 0 {
+                let chunk = remaining.min(ZERO_CHUNK.len());
+                loader.load(addr, &ZERO_CHUNK[..chunk]);
+                addr = addr.checked_add(chunk).ok_or(ElfError::OutOfBounds)?;
+                remaining -= chunk;
+            }
+        }
+
+        Ok(())
     }
 
     /// Applies the relocations necessary for the elf file to work
-    pub fn relocate(&self, base: &mut [u8]) {
-        let section_headers = self.section_headers();
+    pub fn relocate(&self, base: &mut [u8]) -> Result<(), ElfError> {
+        let class = self.class()?;
+
+        if class != Class::Elf64 {
+            return Err(ElfError::UnsupportedRelocationClass(class));
+        }
 
-        for header in section_headers {
+        for header in self.section_headers()? {
             if header.header_type != 0x4 {
                 continue;
             }
 
-            let start_file = self.data.as_ptr() as usize + header.offset as usize;
-            let num = header.size as usize / header.entry_size as usize;
+            if header.entry_size == 0 {
+                continue;
+            }
+
+            let start_file = header.offset as usize;
+            let entry_size = header.entry_size as usize;
 
-            let sections = unsafe { slice::from_raw_parts(start_file as *mut RelocationSection, num) };
+            let min_entry_size = match class {
+                Class::Elf32 => 12,
+                Class::Elf64 => core::mem::size_of::<RelocationSection>(),
+            };
 
-            for section in sections {
-                unsafe {
-                    let ptr = base.as_mut_ptr().add(section.offset) as *mut u64;
-                    *ptr = base.as_ptr() as u64 + section.addend as u64;
+            if entry_size < min_entry_size {
+                return Err(ElfError::OutOfBounds);
+            }
+
+            let num = header.size as usize / entry_size;
+            let table_len = entry_size.checked_mul(num).ok_or(ElfError::OutOfBounds)?;
+            let end_file = start_file.checked_add(table_len).ok_or(ElfError::OutOfBounds)?;
+
+            if end_file > self.data.len() {
+                return Err(ElfError::OutOfBounds);
+            }
+
+            for i in 0..num {
+                let entry_offset = start_file + i * entry_size;
+                let bytes = &self.data[entry_offset..entry_offset + entry_size];
+
+                let relocation = decode_relocation(bytes, class);
+
+                let where_offset = relocation.offset as usize;
+                let addend = relocation.addend;
+                let base_addr = base.as_ptr() as u64;
+
+                match relocation.relocation_type(class) {
+                    // R_X86_64_RELATIVE
+                    8 => self.write_relocation(base, where_offset, base_addr.wrapping_add(addend))?,
+                    // R_X86_64_64
+                    1 => {
+                        let value = self.symbol_value(relocation.symbol_index(class))?.wrapping_add(addend);
+                        self.write_relocation(base, where_offset, value)?;
+                    }
+                    // R_X86_64_GLOB_DAT, R_X86_64_JUMP_SLOT
+                    6 | 7 => {
+                        let value = self.symbol_value(relocation.symbol_index(class))?;
+                        self.write_relocation(base, where_offset, value)?;
+                    }
+                    // R_X86_64_32
+                    10 => {
+                        let value = self.symbol_value(relocation.symbol_index(class))?.wrapping_add(addend) as u32;
+                        self.write_relocation32(base, where_offset, value)?;
+                    }
+                    // R_X86_64_PC32
+                    2 => {
+                        let here = base_addr.wrapping_add(where_offset as u64);
+                        let value = self.symbol_value(relocation.symbol_index(class))?.wrapping_add(addend).wrapping_sub(here) as u32;
+                        self.write_relocation32(base, where_offset, value)?;
+                    }
+                    unknown => return Err(ElfError::UnknownRelocationType(unknown)),
                 }
             }
         }
+
+        Ok(())
+    }
+
+
+    /// Writes a 64-bit relocated value at `offset` bytes into `base`
+    fn write_relocation(&self, base: &mut [u8], offset: usize, value: u64) -> Result<(), ElfError> {
+        let end = offset.checked_add(8).ok_or(ElfError::OutOfBounds)?;
+
+        if end > base.len() {
+            return Err(ElfError::OutOfBounds);
+        }
+
+        base[offset..end].copy_from_slice(&value.to_le_bytes());
+
+        Ok(())
+    }
+
+    /// Writes a truncated 32-bit relocated value at `offset` bytes into `base`
+    fn write_relocation32(&self, base: &mut [u8], offset: usize, value: u32) -> Result<(), ElfError> {
+        let end = offset.checked_add(4).ok_or(ElfError::OutOfBounds)?;
+
+        if end > base.len() {
+            return Err(ElfError::OutOfBounds);
+        }
+
+        base[offset..end].copy_from_slice(&value.to_le_bytes());
+
+        Ok(())
     }
 }
 
@@ -162,6 +620,26 @@ pub struct ProgramHeader {
     pub align: u64
 }
 
+/// Decodes one program header out of `bytes`. ELF64's layout (type, flags, offset,
+/// vaddr, paddr, filesz, memsz, align) is read in place; ELF32's differently-ordered
+/// 32-byte layout (type, offset, vaddr, paddr, filesz, memsz, flags, align) is widened
+/// field-by-field into the same shape.
+fn decode_program_header(bytes: &[u8], class: Class) -> ProgramHeader {
+    match class {
+        Class::Elf32 => ProgramHeader {
+            header_type: u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+            offset: u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as u64,
+            v_addr: u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as u64,
+            p_addr: u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as u64,
+            file_size: u32::from_le_bytes(bytes[16..20].try_into().unwrap()) as u64,
+            memory_size: u32::from_le_bytes(bytes[20..24].try_into().unwrap()) as u64,
+            flags: u32::from_le_bytes(bytes[24..28].try_into().unwrap()),
+            align: u32::from_le_bytes(bytes[28..32].try_into().unwrap()) as u64,
+        },
+        Class::Elf64 => unsafe { (bytes.as_ptr() as *const ProgramHeader).read_unaligned() },
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[repr(packed)]
 pub struct SectionHeader {
@@ -177,24 +655,421 @@ pub struct SectionHeader {
     pub entry_size: u64
 }
 
+/// Decodes one section header out of `bytes`. The field order matches between ELF32
+/// and ELF64; only the 4-byte vs 8-byte widths of the address/offset-like fields differ.
+fn decode_section_header(bytes: &[u8], class: Class) -> SectionHeader {
+    match class {
+        Class::Elf32 => SectionHeader {
+            name_offset: u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+            header_type: u32::from_le_bytes(bytes[4..8].try_into().unwrap()),
+            flags: u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as u64,
+            addr: u32::from_le_bytes(bytes[12..16].try_into().unwrap()) as u64,
+            offset: u32::from_le_bytes(bytes[16..20].try_into().unwrap()) as u64,
+            size: u32::from_le_bytes(bytes[20..24].try_into().unwrap()) as u64,
+            link: u32::from_le_bytes(bytes[24..28].try_into().unwrap()),
+            info: u32::from_le_bytes(bytes[28..32].try_into().unwrap()),
+            addr_align: u32::from_le_bytes(bytes[32..36].try_into().unwrap()) as u64,
+            entry_size: u32::from_le_bytes(bytes[36..40].try_into().unwrap()) as u64,
+        },
+        Class::Elf64 => unsafe { (bytes.as_ptr() as *const SectionHeader).read_unaligned() },
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[repr(packed)]
 pub struct RelocationSection {
-    pub offset: usize,
-    pub info: usize,
-    pub addend: usize,
+    pub offset: u64,
+    pub info: u64,
+    pub addend: u64,
+}
+
+/// Decodes one RELA entry out of `bytes`, widening ELF32's 12-byte
+/// (r_offset, r_info, r_addend: u32) layout into the common u64-typed shape
+fn decode_relocation(bytes: &[u8], class: Class) -> RelocationSection {
+    match class {
+        Class::Elf32 => RelocationSection {
+            offset: u32::from_le_bytes(bytes[0..4].try_into().unwrap()) as u64,
+            info: u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as u64,
+            addend: u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as u64,
+        },
+        Class::Elf64 => unsafe { (bytes.as_ptr() as *const RelocationSection).read_unaligned() },
+    }
+}
+
+impl RelocationSection {
+    /// The symbol table index this relocation refers to: r_info >> 32 for ELF64's
+    /// 64-bit r_info, r_info >> 8 for ELF32's 32-bit r_info
+    fn symbol_index(&self, class: Class) -> u32 {
+        match class {
+            Class::Elf32 => (self.info >> 8) as u32,
+            Class::Elf64 => (self.info >> 32) as u32,
+        }
+    }
+
+    /// The relocation type this relocation applies: r_info & 0xffff_ffff for ELF64,
+    /// r_info & 0xff for ELF32
+    fn relocation_type(&self, class: Class) -> u32 {
+        match class {
+            Class::Elf32 => (self.info & 0xff) as u32,
+            Class::Elf64 => (self.info & 0xffff_ffff) as u32,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(C, packed)]
+pub struct Symbol {
+    pub name_offset: u32,
+    pub info: u8,
+    pub other: u8,
+    pub section_index: u16,
+    pub value: u64,
+    pub size: u64,
+}
+
+/// Decodes one symbol table entry out of `bytes`. ELF64's `Elf64_Sym` layout (name,
+/// info, other, shndx, value, size) is read in place; ELF32's differently-ordered
+/// 16-byte `Elf32_Sym` layout (name, value, size, info, other, shndx) is widened
+/// field-by-field into the same shape.
+fn decode_symbol(bytes: &[u8], class: Class) -> Symbol {
+    match class {
+        Class::Elf32 => Symbol {
+            name_offset: u32::from_le_bytes(bytes[0..4].try_into().unwrap()),
+            value: u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as u64,
+            size: u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as u64,
+            info: bytes[12],
+            other: bytes[13],
+            section_index: u16::from_le_bytes(bytes[14..16].try_into().unwrap()),
+        },
+        Class::Elf64 => unsafe { (bytes.as_ptr() as *const Symbol).read_unaligned() },
+    }
+}
+
+/// Iterates over a validated program header table, widening each entry from its
+/// on-disk ELF32/ELF64 layout into the common `u64`-typed `ProgramHeader` shape
+pub struct ProgramHeaderIter<'a> {
+    data: &'a [u8],
+    class: Class,
+    offset: usize,
+    entry_size: usize,
+    remaining: u16,
+}
+
+impl<'a> Iterator for ProgramHeaderIter<'a> {
+    type Item = ProgramHeader;
+
+    fn next(&mut self) -> Option<ProgramHeader> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let bytes = &self.data[self.offset..self.offset + self.entry_size];
+        let header = decode_program_header(bytes, self.class);
+
+        self.offset += self.entry_size;
+        self.remaining -= 1;
+
+        Some(header)
+    }
+}
+
+/// Iterates over a validated section header table, widening each entry from its
+/// on-disk ELF32/ELF64 layout into the common `u64`-typed `SectionHeader` shape
+pub struct SectionHeaderIter<'a> {
+    data: &'a [u8],
+    class: Class,
+    offset: usize,
+    entry_size: usize,
+    remaining: u16,
+}
+
+impl<'a> Iterator for SectionHeaderIter<'a> {
+    type Item = SectionHeader;
+
+    fn next(&mut self) -> Option<SectionHeader> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let bytes = &self.data[self.offset..self.offset + self.entry_size];
+        let header = decode_section_header(bytes, self.class);
+
+        self.offset += self.entry_size;
+        self.remaining -= 1;
+
+        Some(header)
+    }
+}
+
+/// Iterates over a validated symbol table, widening each entry from its on-disk
+/// ELF32/ELF64 layout into the common `u64`-typed `Symbol` shape
+pub struct SymbolIter<'a> {
+    data: &'a [u8],
+    class: Class,
+    offset: usize,
+    entry_size: usize,
+    remaining: u32,
+}
+
+impl<'a> Iterator for SymbolIter<'a> {
+    type Item = Symbol;
+
+    fn next(&mut self) -> Option<Symbol> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let bytes = &self.data[self.offset..self.offset + self.entry_size];
+        let symbol = decode_symbol(bytes, self.class);
+
+        self.offset += self.entry_size;
+        self.remaining -= 1;
+
+        Some(symbol)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
-    use crate::file::ElfFile;
+    use crate::file::{Class, ElfError, ElfFile, ElfLoader, SegmentFlags};
+
+    /// A minimal 64-byte ELF64 little-endian header with every multi-byte field zeroed;
+    /// tests overwrite the fields they care about before appending program/section data
+    fn elf64_header() -> [u8; 64] {
+        let mut header = [0u8; 64];
+        header[0..4].copy_from_slice(&[0x7F, 0x45, 0x4C, 0x46]);
+        header[4] = 2; // EI_CLASS = ELFCLASS64
+        header[5] = 1; // EI_DATA = ELFDATA2LSB
+        header
+    }
+
+    #[test]
+    fn try_read_rejects_truncated_buffer() {
+        let mut data = vec![0u8; 3];
+
+        assert_eq!(ElfFile::try_read(&mut data).err(), Some(ElfError::TooShort));
+    }
+
+    #[test]
+    fn program_headers_rejects_entry_size_smaller_than_program_header() {
+        let mut data = elf64_header().to_vec();
+        data[32..40].copy_from_slice(&64u64.to_le_bytes()); // e_phoff
+        data[54..56].copy_from_slice(&0u16.to_le_bytes()); // e_phentsize
+        data[56..58].copy_from_slice(&1u16.to_le_bytes()); // e_phnum
+
+        let elf = ElfFile::try_read(&mut data).expect("header should parse");
+
+        assert_eq!(elf.program_headers().err(), Some(ElfError::OutOfBounds));
+    }
+
+    #[test]
+    fn load_segments_len_rejects_overflowing_vaddr() {
+        let mut data = elf64_header().to_vec();
+        data[32..40].copy_from_slice(&64u64.to_le_bytes()); // e_phoff
+        data[54..56].copy_from_slice(&56u16.to_le_bytes()); // e_phentsize
+        data[56..58].copy_from_slice(&1u16.to_le_bytes()); // e_phnum
+
+        let mut program_header = [0u8; 56];
+        program_header[0..4].copy_from_slice(&1u32.to_le_bytes()); // p_type = PT_LOAD
+        program_header[16..24].copy_from_slice(&u64::MAX.to_le_bytes()); // p_vaddr
+        program_header[40..48].copy_from_slice(&0x10u64.to_le_bytes()); // p_memsz
+        data.extend_from_slice(&program_header);
+
+        let elf = ElfFile::try_read(&mut data).expect("header should parse");
+
+        assert_eq!(elf.load_segments_len().err(), Some(ElfError::OutOfBounds));
+    }
+
+    #[test]
+    fn relocate_rejects_unknown_relocation_type() {
+        let mut data = elf64_header().to_vec();
+        data[40..48].copy_from_slice(&64u64.to_le_bytes()); // e_shoff
+        data[58..60].copy_from_slice(&64u16.to_le_bytes()); // e_shentsize
+        data[60..62].copy_from_slice(&1u16.to_le_bytes()); // e_shnum
+
+        let mut section_header = [0u8; 64];
+        section_header[4..8].copy_from_slice(&4u32.to_le_bytes()); // sh_type = SHT_RELA
+        section_header[24..32].copy_from_slice(&128u64.to_le_bytes()); // sh_offset
+        section_header[32..40].copy_from_slice(&24u64.to_le_bytes()); // sh_size
+        section_header[56..64].copy_from_slice(&24u64.to_le_bytes()); // sh_entsize
+        data.extend_from_slice(&section_header);
+
+        let mut relocation = [0u8; 24];
+        relocation[8..16].copy_from_slice(&999u64.to_le_bytes()); // r_info, low 32 bits = type 999
+        data.extend_from_slice(&relocation);
+
+        let elf = ElfFile::try_read(&mut data).expect("header should parse");
+        let mut base = vec![0u8; 16];
+
+        assert_eq!(elf.relocate(&mut base), Err(ElfError::UnknownRelocationType(999)));
+    }
+
+    #[derive(Default)]
+    struct RecordingLoader {
+        allocations: Vec<(usize, usize, SegmentFlags)>,
+        loads: Vec<(usize, Vec<u8>)>,
+    }
+
+    impl ElfLoader for RecordingLoader {
+        fn allocate(&mut self, vaddr: usize, size: usize, flags: SegmentFlags) {
+            self.allocations.push((vaddr, size, flags));
+        }
+
+        fn load(&mut self, vaddr: usize, data: &[u8]) {
+            self.loads.push((vaddr, data.to_vec()));
+        }
+    }
+
+    #[test]
+    fn load_with_allocates_and_zero_fills_bss() {
+        let mut data = elf64_header().to_vec();
+        data[32..40].copy_from_slice(&64u64.to_le_bytes()); // e_phoff
+        data[54..56].copy_from_slice(&56u16.to_le_bytes()); // e_phentsize
+        data[56..58].copy_from_slice(&1u16.to_le_bytes()); // e_phnum
+
+        let mut program_header = [0u8; 56];
+        program_header[0..4].copy_from_slice(&1u32.to_le_bytes()); // p_type = PT_LOAD
+        program_header[4..8].copy_from_slice(&SegmentFlags::READ.to_le_bytes()); // p_flags
+        program_header[8..16].copy_from_slice(&120u64.to_le_bytes()); // p_offset
+        program_header[16..24].copy_from_slice(&0x1000u64.to_le_bytes()); // p_vaddr
+        program_header[32..40].copy_from_slice(&4u64.to_le_bytes()); // p_filesz
+        program_header[40..48].copy_from_slice(&8u64.to_le_bytes()); // p_memsz
+        data.extend_from_slice(&program_header);
+        data.extend_from_slice(&[1, 2, 3, 4]);
+
+        let elf = ElfFile::try_read(&mut data).expect("header should parse");
+        let mut loader = RecordingLoader::default();
+
+        elf.load_with(&mut loader).expect("load_with should succeed");
+
+        assert_eq!(loader.allocations, [(0x1000, 8, SegmentFlags::from_bits(SegmentFlags::READ))]);
+        assert_eq!(loader.loads, [(0x1000, vec![1, 2, 3, 4]), (0x1004, vec![0, 0, 0, 0])]);
+    }
+
+    #[test]
+    fn relocate_applies_relative_relocation() {
+        let mut data = elf64_header().to_vec();
+        data[40..48].copy_from_slice(&64u64.to_le_bytes()); // e_shoff
+        data[58..60].copy_from_slice(&64u16.to_le_bytes()); // e_shentsize
+        data[60..62].copy_from_slice(&1u16.to_le_bytes()); // e_shnum
+
+        let mut section_header = [0u8; 64];
+        section_header[4..8].copy_from_slice(&4u32.to_le_bytes()); // sh_type = SHT_RELA
+        section_header[24..32].copy_from_slice(&128u64.to_le_bytes()); // sh_offset
+        section_header[32..40].copy_from_slice(&24u64.to_le_bytes()); // sh_size
+        section_header[56..64].copy_from_slice(&24u64.to_le_bytes()); // sh_entsize
+        data.extend_from_slice(&section_header);
+
+        let mut relocation = [0u8; 24];
+        relocation[0..8].copy_from_slice(&0u64.to_le_bytes()); // r_offset
+        relocation[8..16].copy_from_slice(&8u64.to_le_bytes()); // r_info, type = R_X86_64_RELATIVE
+        relocation[16..24].copy_from_slice(&0x10u64.to_le_bytes()); // r_addend
+        data.extend_from_slice(&relocation);
+
+        let elf = ElfFile::try_read(&mut data).expect("header should parse");
+        let mut base = vec![0u8; 16];
+        let base_addr = base.as_ptr() as u64;
+
+        elf.relocate(&mut base).expect("relocation should apply");
+
+        let expected = base_addr.wrapping_add(0x10);
+        assert_eq!(&base[0..8], &expected.to_le_bytes());
+    }
 
     #[test]
-    fn test_entry_point() {
-        let data = fs::read("./kernel").expect("Unable to read test file");
-        let elf = ElfFile::read(data);
+    fn section_and_symbol_names_resolve_via_string_tables() {
+        let mut shstrtab = vec![0u8];
+        let shstrtab_name_offset = shstrtab.len() as u32;
+        shstrtab.extend_from_slice(b".shstrtab\0");
+        let text_name_offset = shstrtab.len() as u32;
+        shstrtab.extend_from_slice(b".text\0");
+        let symtab_name_offset = shstrtab.len() as u32;
+        shstrtab.extend_from_slice(b".symtab\0");
+        let strtab_name_offset = shstrtab.len() as u32;
+        shstrtab.extend_from_slice(b".strtab\0");
+
+        let mut strtab = vec![0u8];
+        let sym_name_offset = strtab.len() as u32;
+        strtab.extend_from_slice(b"sym_name\0");
+
+        let mut symbol = [0u8; 24];
+        symbol[0..4].copy_from_slice(&sym_name_offset.to_le_bytes()); // st_name
+        symbol[6..8].copy_from_slice(&1u16.to_le_bytes()); // st_shndx = .text
+        symbol[8..16].copy_from_slice(&0x2000u64.to_le_bytes()); // st_value
+        symbol[16..24].copy_from_slice(&0x8u64.to_le_bytes()); // st_size
+
+        const HEADER_LEN: usize = 64;
+        const SECTION_HEADER_LEN: usize = 64;
+        const SECTION_COUNT: usize = 4;
+        let section_table_end = HEADER_LEN + SECTION_HEADER_LEN * SECTION_COUNT;
+
+        let shstrtab_offset = section_table_end;
+        let symtab_offset = shstrtab_offset + shstrtab.len();
+        let strtab_offset = symtab_offset + symbol.len();
+
+        let section_header = |name_offset: u32, header_type: u32, offset: usize, size: usize, link: u32, entry_size: u64| {
+            let mut bytes = [0u8; SECTION_HEADER_LEN];
+            bytes[0..4].copy_from_slice(&name_offset.to_le_bytes());
+            bytes[4..8].copy_from_slice(&header_type.to_le_bytes());
+            bytes[24..32].copy_from_slice(&(offset as u64).to_le_bytes());
+            bytes[32..40].copy_from_slice(&(size as u64).to_le_bytes());
+            bytes[40..44].copy_from_slice(&link.to_le_bytes());
+            bytes[56..64].copy_from_slice(&entry_size.to_le_bytes());
+            bytes
+        };
+
+        let mut data = elf64_header().to_vec();
+        data[40..48].copy_from_slice(&(HEADER_LEN as u64).to_le_bytes()); // e_shoff
+        data[58..60].copy_from_slice(&(SECTION_HEADER_LEN as u16).to_le_bytes()); // e_shentsize
+        data[60..62].copy_from_slice(&(SECTION_COUNT as u16).to_le_bytes()); // e_shnum
+        data[62..64].copy_from_slice(&0u16.to_le_bytes()); // e_shstrndx
+
+        data.extend_from_slice(&section_header(shstrtab_name_offset, 3, shstrtab_offset, shstrtab.len(), 0, 0));
+        data.extend_from_slice(&section_header(text_name_offset, 1, 0, 0, 0, 0));
+        data.extend_from_slice(&section_header(symtab_name_offset, 2, symtab_offset, symbol.len(), 3, 24));
+        data.extend_from_slice(&section_header(strtab_name_offset, 3, strtab_offset, strtab.len(), 0, 0));
+
+        data.extend_from_slice(&shstrtab);
+        data.extend_from_slice(&symbol);
+        data.extend_from_slice(&strtab);
+
+        let elf = ElfFile::try_read(&mut data).expect("header should parse");
+
+        let text = elf.find_section(".text").expect("`.text` section should be found");
+        assert_eq!(elf.section_name(&text), Some(".text"));
+
+        let symtab_header = elf.section_headers().expect("section headers").nth(2).unwrap();
+        let symbol = elf.symbols().expect("symbol table").next().expect("one symbol");
+        assert_eq!(elf.symbol_name(&symtab_header, &symbol), Some("sym_name"));
+    }
+
+    #[test]
+    fn parses_minimal_elf32_program_headers_and_entrypoint() {
+        let mut data = vec![0u8; 52];
+        data[0..4].copy_from_slice(&[0x7F, 0x45, 0x4C, 0x46]);
+        data[4] = 1; // EI_CLASS = ELFCLASS32
+        data[5] = 1; // EI_DATA = ELFDATA2LSB
+        data[24..28].copy_from_slice(&0x8048000u32.to_le_bytes()); // e_entry
+        data[28..32].copy_from_slice(&52u32.to_le_bytes()); // e_phoff
+        data[42..44].copy_from_slice(&32u16.to_le_bytes()); // e_phentsize
+        data[44..46].copy_from_slice(&1u16.to_le_bytes()); // e_phnum
+
+        let mut program_header = [0u8; 32];
+        program_header[0..4].copy_from_slice(&1u32.to_le_bytes()); // p_type = PT_LOAD
+        program_header[8..12].copy_from_slice(&0x8048000u32.to_le_bytes()); // p_vaddr
+        program_header[16..20].copy_from_slice(&0x100u32.to_le_bytes()); // p_filesz
+        program_header[20..24].copy_from_slice(&0x200u32.to_le_bytes()); // p_memsz
+        data.extend_from_slice(&program_header);
+
+        let elf = ElfFile::try_read(&mut data).expect("minimal ELF32 header should parse");
+
+        assert_eq!(elf.identify().unwrap().class, Class::Elf32);
+        assert_eq!(elf.entrypoint(), Ok(0x8048000));
 
-        assert!(elf.is_valid());
+        let header = elf.program_headers().expect("program headers").next().expect("one program header");
+        let (v_addr, file_size, memory_size) = (header.v_addr, header.file_size, header.memory_size);
+        assert_eq!(v_addr, 0x8048000);
+        assert_eq!(file_size, 0x100);
+        assert_eq!(memory_size, 0x200);
     }
 }
\ No newline at end of file